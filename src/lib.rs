@@ -4,6 +4,13 @@
 //! Provides a circular buffer that is implemented as a heap-allocated generic of Optional objects.
 //! Using Optional allows setting the contents (to None::<T>) when they are unused.
 
+use std::cell::UnsafeCell;
+use std::ops::Range;
+
+mod spsc;
+
+pub use spsc::{Consumer, Producer};
+
 
 /// Implements a circular buffer generic
 ///
@@ -17,6 +24,12 @@ pub struct CircularBuffer<T> {
     start: usize,
     /// The end index
     end: usize,
+    /// A monotonically increasing count of every element ever pushed (via `write` or `overwrite`)
+    total_written: usize,
+    /// Set once an element has been evicted before being read, e.g. by `overwrite` or a
+    /// shrinking `resize`; unlike comparing `total_written` to `size`, this survives later
+    /// capacity changes
+    wrapped: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -47,6 +60,8 @@ impl<T> CircularBuffer<T> {
             size: capacity,
             start: 0,
             end: 0,
+            total_written: 0,
+            wrapped: false,
         };
         buffer
     }
@@ -74,11 +89,12 @@ impl<T> CircularBuffer<T> {
     /// assert_eq!(Result::Err(Error::FullBuffer), buffer.write(6));
     /// ```
     pub fn write(&mut self, element: T) -> Result<(), Error> {
-        if self.start == self.end && self.data[self.start].is_some() {
+        if self.size == 0 || (self.start == self.end && self.data[self.start].is_some()) {
             Result::Err(Error::FullBuffer)
         } else {
             self.data[self.end] = Some(element);
             self.end = (self.end + 1) % self.size;
+            self.total_written += 1;
             Result::Ok(())
         }
     }
@@ -89,7 +105,7 @@ impl<T> CircularBuffer<T> {
     /// * `Ok(T)` The value is successfully read
     /// * `Err(Error::EmptyBuffer)` The buffer was empty
     pub fn read(&mut self) -> Result<T, Error> {
-        if self.data[self.start].is_none() {
+        if self.size == 0 || self.data[self.start].is_none() {
             Result::Err(Error::EmptyBuffer)
         } else {
             if let Some(read_value) = self.data[self.start].take() {
@@ -113,17 +129,404 @@ impl<T> CircularBuffer<T> {
         }
     }
 
+    /// Writes each element of `elements` in order, stopping at the first failure
+    ///
+    /// # Returns
+    /// * `Ok(())` if every element was written
+    /// * `Err(Error::FullBuffer)` as soon as the buffer fills up
+    ///
+    /// Elements already written before the failing one remain in the buffer; the
+    /// caller can inspect how many were consumed (e.g. via `len`) and retry with
+    /// the remainder of the iterator.
+    pub fn write_many(&mut self, elements: impl IntoIterator<Item = T>) -> Result<(), Error> {
+        for element in elements {
+            self.write(element)?;
+        }
+        Result::Ok(())
+    }
+
+    /// Reads up to `n` of the oldest elements off the buffer into a `Vec`, in FIFO order
+    ///
+    /// Stops early (returning fewer than `n` elements) once the buffer is empty.
+    pub fn read_many(&mut self, n: usize) -> Vec<T> {
+        let mut elements = Vec::with_capacity(n.min(self.len()));
+        for _ in 0..n {
+            match self.read() {
+                Result::Ok(element) => elements.push(element),
+                Result::Err(_) => break,
+            }
+        }
+        elements
+    }
+
+    /// Drains the whole buffer in FIFO order into a `Vec`, leaving it empty
+    ///
+    /// Unlike `clear`, which discards the contents, `take` hands them back to the caller.
+    pub fn take(&mut self) -> Vec<T> {
+        self.read_many(self.len())
+    }
+
     /// Forces writing a value, even when the buffer is full
     ///
     /// For a non-full buffer, this is equivalent to a normal write.  If the buffer is full, this is
     /// equivalent to a read and a write.
     pub fn overwrite(&mut self, element: T) {
-        if self.start == self.end && self.data[self.start].is_some() {
+        if self.size == 0 {
+            // Nothing to overwrite into; drop the element the same way `write` would reject it.
+        } else if self.start == self.end && self.data[self.start].is_some() {
             self.data[self.end] = Some(element);
             self.start = (self.start + 1) % self.size;
             self.end = self.start;
+            self.total_written += 1;
+            self.wrapped = true;
         } else {
             let _ = self.write(element); // Ignore the result; above check should suffice
         }
     }
+
+    /// Returns the capacity of the buffer
+    pub fn capacity(&self) -> usize {
+        self.size
+    }
+
+    /// Returns `true` if the buffer currently holds no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the buffer currently holds `capacity()` elements
+    pub fn is_full(&self) -> bool {
+        self.len() == self.size
+    }
+
+    /// Returns the total number of elements ever pushed into the buffer via `write` or `overwrite`
+    ///
+    /// This counter never resets (not even on `clear`), so it can be used alongside
+    /// `has_wrapped` to detect whether older entries have been evicted.
+    pub fn total_elements(&self) -> usize {
+        self.total_written
+    }
+
+    /// Returns `true` once an element has been evicted before being read
+    ///
+    /// This happens either because `overwrite` forced one out, or because a shrinking
+    /// `resize`/`grow` dropped the oldest overflowing elements. `total_written > capacity`
+    /// alone isn't enough to detect this after a `resize`, since `capacity` can change.
+    pub fn has_wrapped(&self) -> bool {
+        self.wrapped || self.total_written > self.size
+    }
+
+    /// Returns the number of elements currently stored in the buffer
+    pub fn len(&self) -> usize {
+        if self.size == 0 {
+            0
+        } else if self.start == self.end {
+            if self.data[self.start].is_some() {
+                self.size
+            } else {
+                0
+            }
+        } else if self.end > self.start {
+            self.end - self.start
+        } else {
+            self.size - self.start + self.end
+        }
+    }
+
+    /// Returns a non-consuming iterator over the buffer's elements in FIFO order
+    ///
+    /// Unlike `read`, this does not remove elements from the buffer; it only
+    /// borrows them for inspection (e.g. displaying the last N log lines).
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            buffer: self,
+            pos: self.start,
+            remaining: self.len(),
+        }
+    }
+
+    /// Returns a non-consuming iterator over the buffer's elements in reverse (LIFO) order
+    pub fn rev_iter(&self) -> RevIter<'_, T> {
+        RevIter {
+            buffer: self,
+            pos: self.end,
+            remaining: self.len(),
+        }
+    }
+
+    /// Splits a run of `count` slots starting at `start` (wrapping around `size`) into at
+    /// most two contiguous index ranges
+    ///
+    /// Used to walk the occupied or free slots of the buffer as slices instead of
+    /// stepping through them one `Option` at a time.
+    fn contiguous_ranges(start: usize, count: usize, size: usize) -> (Range<usize>, Range<usize>) {
+        if count == 0 {
+            (0..0, 0..0)
+        } else if start + count <= size {
+            (start..start + count, 0..0)
+        } else {
+            (start..size, 0..(start + count - size))
+        }
+    }
+
+    /// Grows the buffer's capacity by `additional`, preserving the current elements
+    ///
+    /// Equivalent to `resize(capacity() + additional)`.
+    pub fn grow(&mut self, additional: usize) {
+        self.resize(self.size + additional);
+    }
+
+    /// Reallocates the buffer to `new_capacity`, preserving the current elements in
+    /// their logical FIFO order
+    ///
+    /// If `new_capacity` is smaller than `len()`, the oldest overflowing elements are
+    /// dropped to make room, the same way `overwrite` would evict them.
+    pub fn resize(&mut self, new_capacity: usize) {
+        let keep = self.len().min(new_capacity);
+        if self.len() > keep {
+            self.wrapped = true;
+        }
+        while self.len() > keep {
+            let _ = self.read();
+        }
+        let mut new_data: Vec<Option<T>> = Vec::with_capacity(new_capacity);
+        for _ in 0..keep {
+            new_data.push(Some(self.read().expect("kept elements are readable")));
+        }
+        new_data.resize_with(new_capacity, || None::<T>);
+        self.data = new_data.into_boxed_slice();
+        self.size = new_capacity;
+        self.start = 0;
+        self.end = if keep == new_capacity { 0 } else { keep };
+    }
+
+    /// Splits the buffer into a write-only [`Producer`] and a read-only [`Consumer`]
+    ///
+    /// This divides ownership for lock-free use across a single producer thread and
+    /// a single consumer thread (the classic SPSC ring queue), backed internally by
+    /// atomics instead of the plain `usize` indices used here. Elements already in
+    /// the buffer are preserved, in FIFO order, for the consumer to read first.
+    pub fn split(mut self) -> (Producer<T>, Consumer<T>) {
+        let capacity = self.size;
+        let len = self.len();
+        let mut data: Vec<UnsafeCell<Option<T>>> = Vec::with_capacity(capacity);
+        for _ in 0..len {
+            data.push(UnsafeCell::new(Some(self.read().expect("len() elements are readable"))));
+        }
+        for _ in len..capacity {
+            data.push(UnsafeCell::new(None));
+        }
+        spsc::split(data.into_boxed_slice(), capacity, len)
+    }
+}
+
+/// A non-consuming iterator over the elements of a `CircularBuffer`, in FIFO order
+///
+/// Created by [`CircularBuffer::iter`].
+pub struct Iter<'a, T> {
+    buffer: &'a CircularBuffer<T>,
+    pos: usize,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let item = self.buffer.data[self.pos].as_ref();
+        self.pos = (self.pos + 1) % self.buffer.size;
+        self.remaining -= 1;
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// A non-consuming iterator over the elements of a `CircularBuffer`, in reverse (LIFO) order
+///
+/// Created by [`CircularBuffer::rev_iter`].
+pub struct RevIter<'a, T> {
+    buffer: &'a CircularBuffer<T>,
+    pos: usize,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for RevIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.pos = (self.pos + self.buffer.size - 1) % self.buffer.size;
+        self.remaining -= 1;
+        self.buffer.data[self.pos].as_ref()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// Lets a `CircularBuffer<u8>` act as an in-memory pipe: bytes written in are read back out
+/// in the same order, making it usable anywhere an `impl Read` or `impl Write` is expected.
+impl std::io::Write for CircularBuffer<u8> {
+    /// Copies as many bytes from `buf` as there is free space for
+    ///
+    /// Never errors; returns `0` (rather than `Ok(0)` being mistaken for EOF) only when
+    /// the buffer is full.
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.size == 0 {
+            return Ok(0);
+        }
+        let free = self.size - self.len();
+        let n = buf.len().min(free);
+        let (first, second) = Self::contiguous_ranges(self.end, n, self.size);
+        let split = first.len();
+        self.data[first]
+            .iter_mut()
+            .zip(&buf[..split])
+            .for_each(|(slot, &byte)| *slot = Some(byte));
+        self.data[second]
+            .iter_mut()
+            .zip(&buf[split..n])
+            .for_each(|(slot, &byte)| *slot = Some(byte));
+        self.end = (self.end + n) % self.size;
+        self.total_written += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl std::io::Read for CircularBuffer<u8> {
+    /// Copies the oldest bytes in the buffer into `buf`, returning `Ok(0)` when empty
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.size == 0 {
+            return Ok(0);
+        }
+        let n = buf.len().min(self.len());
+        let (first, second) = Self::contiguous_ranges(self.start, n, self.size);
+        let split = first.len();
+        self.data[first]
+            .iter_mut()
+            .zip(&mut buf[..split])
+            .for_each(|(slot, byte)| *byte = slot.take().expect("readable range holds a value"));
+        self.data[second]
+            .iter_mut()
+            .zip(&mut buf[split..n])
+            .for_each(|(slot, byte)| *byte = slot.take().expect("readable range holds a value"));
+        self.start = (self.start + n) % self.size;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iter_distinguishes_full_from_empty() {
+        let mut buffer = CircularBuffer::<u32>::new(3);
+        assert_eq!(buffer.iter().collect::<Vec<_>>(), Vec::<&u32>::new());
+        buffer.write(1).unwrap();
+        buffer.write(2).unwrap();
+        buffer.write(3).unwrap();
+        // start == end here too, but the buffer is full rather than empty
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(buffer.rev_iter().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn iter_after_wraparound_preserves_fifo_order() {
+        let mut buffer = CircularBuffer::<u32>::new(3);
+        buffer.write(1).unwrap();
+        buffer.write(2).unwrap();
+        buffer.read().unwrap();
+        buffer.write(3).unwrap();
+        buffer.write(4).unwrap(); // end wraps back around to index 0
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn has_wrapped_reflects_eviction_by_overwrite() {
+        let mut buffer = CircularBuffer::<u32>::new(3);
+        buffer.write(1).unwrap();
+        buffer.write(2).unwrap();
+        buffer.write(3).unwrap();
+        assert!(!buffer.has_wrapped());
+        buffer.overwrite(4); // evicts the 1
+        assert!(buffer.has_wrapped());
+    }
+
+    #[test]
+    fn has_wrapped_survives_growing_the_capacity() {
+        let mut buffer = CircularBuffer::<u32>::new(3);
+        buffer.write(1).unwrap();
+        buffer.write(2).unwrap();
+        buffer.write(3).unwrap();
+        buffer.overwrite(4);
+        assert!(buffer.has_wrapped());
+        buffer.grow(5);
+        assert!(buffer.has_wrapped(), "growing must not erase eviction history");
+    }
+
+    #[test]
+    fn write_many_stops_at_first_failure_and_keeps_what_fit() {
+        let mut buffer = CircularBuffer::<u32>::new(3);
+        let result = buffer.write_many(vec![1, 2, 3, 4, 5]);
+        assert_eq!(result, Err(Error::FullBuffer));
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.take(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn io_read_write_round_trip_across_wraparound() {
+        use std::io::{Read, Write};
+        let mut buffer = CircularBuffer::<u8>::new(4);
+        Write::write(&mut buffer, &[1, 2, 3]).unwrap();
+        let mut out = [0u8; 2];
+        Read::read(&mut buffer, &mut out).unwrap();
+        assert_eq!(out, [1, 2]);
+        Write::write(&mut buffer, &[4, 5, 6]).unwrap(); // end wraps around
+        let mut rest = [0u8; 4];
+        let n = Read::read(&mut buffer, &mut rest).unwrap();
+        assert_eq!(&rest[..n], &[3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn io_write_returns_zero_rather_than_erroring_when_full() {
+        use std::io::Write;
+        let mut buffer = CircularBuffer::<u8>::new(2);
+        assert_eq!(Write::write(&mut buffer, &[1, 2]).unwrap(), 2);
+        assert_eq!(Write::write(&mut buffer, &[3]).unwrap(), 0);
+    }
+
+    #[test]
+    fn resize_shrink_drops_oldest_elements_and_marks_wrapped() {
+        let mut buffer = CircularBuffer::<u32>::new(4);
+        buffer.write(1).unwrap();
+        buffer.write(2).unwrap();
+        buffer.write(3).unwrap();
+        buffer.resize(2);
+        assert_eq!(buffer.capacity(), 2);
+        assert_eq!(buffer.take(), vec![2, 3]);
+        assert!(buffer.has_wrapped());
+    }
+
+    #[test]
+    fn resize_to_zero_leaves_the_buffer_inert_instead_of_panicking() {
+        let mut buffer = CircularBuffer::<u32>::new(5);
+        buffer.resize(0);
+        assert_eq!(buffer.len(), 0);
+        assert_eq!(buffer.write(1), Err(Error::FullBuffer));
+        assert_eq!(buffer.read(), Err(Error::EmptyBuffer));
+    }
 }
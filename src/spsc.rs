@@ -0,0 +1,205 @@
+//! Single-producer / single-consumer halves of a `CircularBuffer`
+//!
+//! [`crate::CircularBuffer::split`] divides an owned buffer into a write-only
+//! [`Producer`] and a read-only [`Consumer`] that can each be moved to a
+//! different thread. The two sides never touch the same index: the producer
+//! only ever advances `end`, the consumer only ever advances `start`, and
+//! each side reads the *other* side's index with `Acquire` after writing its
+//! own with `Release`, so the element itself is always visible by the time
+//! its index update is observed.
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::Error;
+
+/// Shared storage between a [`Producer`] and a [`Consumer`]
+///
+/// `start` and `end` are monotonically increasing counters (not wrapped to
+/// `capacity`); the physical slot for a counter value is `counter % capacity`.
+/// This sidesteps the usual full-vs-empty ambiguity of wrapped indices: the
+/// buffer is full when `end - start == capacity` and empty when `end == start`.
+struct Inner<T> {
+    data: Box<[UnsafeCell<Option<T>>]>,
+    capacity: usize,
+    start: AtomicUsize,
+    end: AtomicUsize,
+}
+
+// SAFETY: each slot in `data` is written by at most one side (the producer
+// for `Some`, the consumer for taking it back to `None`) at any given time;
+// the `start`/`end` atomics are what hand off exclusive access between them.
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+impl<T> Inner<T> {
+    fn new(data: Box<[UnsafeCell<Option<T>>]>, capacity: usize, len: usize) -> Self {
+        Inner {
+            data,
+            capacity,
+            start: AtomicUsize::new(0),
+            end: AtomicUsize::new(len),
+        }
+    }
+}
+
+/// Builds the shared halves for [`crate::CircularBuffer::split`]
+///
+/// `data` must already hold `len` live elements at indices `0..len`, in FIFO order.
+pub(crate) fn split<T>(
+    data: Box<[UnsafeCell<Option<T>>]>,
+    capacity: usize,
+    len: usize,
+) -> (Producer<T>, Consumer<T>) {
+    let inner = Arc::new(Inner::new(data, capacity, len));
+    (Producer { inner: inner.clone() }, Consumer { inner })
+}
+
+/// The write-only half of a split `CircularBuffer`
+///
+/// Created by [`crate::CircularBuffer::split`].
+pub struct Producer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+// SAFETY: `Producer` only ever touches the `end` counter and the slots it
+// addresses; moving it to another thread is sound as long as `T` is.
+unsafe impl<T: Send> Send for Producer<T> {}
+
+impl<T> Producer<T> {
+    /// Alias for [`Self::try_push`]
+    ///
+    /// There's no blocking variant in this non-threaded-runtime crate, so `push` and
+    /// `try_push` behave identically; both are provided so callers porting from
+    /// other SPSC queues can use whichever name they expect.
+    pub fn push(&mut self, element: T) -> Result<(), Error> {
+        self.try_push(element)
+    }
+
+    /// Attempts to push a value, returning `Error::FullBuffer` if there's no room
+    pub fn try_push(&mut self, element: T) -> Result<(), Error> {
+        let end = self.inner.end.load(Ordering::Relaxed);
+        let start = self.inner.start.load(Ordering::Acquire);
+        if end - start == self.inner.capacity {
+            return Result::Err(Error::FullBuffer);
+        }
+        let slot = &self.inner.data[end % self.inner.capacity];
+        // SAFETY: this slot is not readable by the consumer until `end` is published below
+        unsafe { *slot.get() = Some(element) };
+        self.inner.end.store(end + 1, Ordering::Release);
+        Result::Ok(())
+    }
+
+    /// Returns `true` if the buffer is full from the producer's point of view
+    pub fn is_full(&self) -> bool {
+        let end = self.inner.end.load(Ordering::Relaxed);
+        let start = self.inner.start.load(Ordering::Acquire);
+        end - start == self.inner.capacity
+    }
+}
+
+/// The read-only half of a split `CircularBuffer`
+///
+/// Created by [`crate::CircularBuffer::split`].
+pub struct Consumer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+// SAFETY: `Consumer` only ever touches the `start` counter and the slots it
+// addresses; moving it to another thread is sound as long as `T` is.
+unsafe impl<T: Send> Send for Consumer<T> {}
+
+impl<T> Consumer<T> {
+    /// Alias for [`Self::try_pop`]
+    ///
+    /// There's no blocking variant in this non-threaded-runtime crate, so `pop` and
+    /// `try_pop` behave identically; both are provided so callers porting from
+    /// other SPSC queues can use whichever name they expect.
+    pub fn pop(&mut self) -> Result<T, Error> {
+        self.try_pop()
+    }
+
+    /// Attempts to pop a value, returning `Error::EmptyBuffer` if there's none available
+    pub fn try_pop(&mut self) -> Result<T, Error> {
+        let start = self.inner.start.load(Ordering::Relaxed);
+        let end = self.inner.end.load(Ordering::Acquire);
+        if start == end {
+            return Result::Err(Error::EmptyBuffer);
+        }
+        let slot = &self.inner.data[start % self.inner.capacity];
+        // SAFETY: this slot was published by the producer before `end` was advanced past it
+        let element = unsafe { (*slot.get()).take() }.expect("occupied slot holds a value");
+        self.inner.start.store(start + 1, Ordering::Release);
+        Result::Ok(element)
+    }
+
+    /// Returns `true` if the buffer is empty from the consumer's point of view
+    pub fn is_empty(&self) -> bool {
+        let start = self.inner.start.load(Ordering::Relaxed);
+        let end = self.inner.end.load(Ordering::Acquire);
+        start == end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_pair<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+        let data: Vec<UnsafeCell<Option<T>>> = (0..capacity).map(|_| UnsafeCell::new(None)).collect();
+        split(data.into_boxed_slice(), capacity, 0)
+    }
+
+    #[test]
+    fn try_pop_fails_on_an_empty_buffer() {
+        let (_producer, mut consumer) = empty_pair::<u32>(2);
+        assert_eq!(consumer.try_pop(), Err(Error::EmptyBuffer));
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn try_push_fails_once_the_buffer_is_full() {
+        let (mut producer, _consumer) = empty_pair::<u32>(2);
+        producer.try_push(1).unwrap();
+        producer.try_push(2).unwrap();
+        assert_eq!(producer.try_push(3), Err(Error::FullBuffer));
+        assert!(producer.is_full());
+    }
+
+    #[test]
+    fn full_buffer_frees_a_slot_after_a_pop() {
+        let (mut producer, mut consumer) = empty_pair::<u32>(2);
+        producer.try_push(1).unwrap();
+        producer.try_push(2).unwrap();
+        assert_eq!(consumer.try_pop(), Ok(1));
+        assert!(!producer.is_full());
+        producer.try_push(3).unwrap();
+        assert_eq!(consumer.try_pop(), Ok(2));
+        assert_eq!(consumer.try_pop(), Ok(3));
+        assert_eq!(consumer.try_pop(), Err(Error::EmptyBuffer));
+    }
+
+    #[test]
+    fn concurrent_producer_and_consumer_round_trip_every_element_in_order() {
+        let (mut producer, mut consumer) = empty_pair::<u32>(8);
+        let producer_thread = std::thread::spawn(move || {
+            for i in 0..1000 {
+                while producer.try_push(i).is_err() {
+                    std::hint::spin_loop();
+                }
+            }
+        });
+
+        let mut received = Vec::with_capacity(1000);
+        while received.len() < 1000 {
+            match consumer.try_pop() {
+                Ok(value) => received.push(value),
+                Err(Error::EmptyBuffer) => std::hint::spin_loop(),
+                Err(Error::FullBuffer) => unreachable!("try_pop never returns FullBuffer"),
+            }
+        }
+
+        producer_thread.join().unwrap();
+        assert_eq!(received, (0..1000).collect::<Vec<_>>());
+    }
+}